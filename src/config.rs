@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use config::Config;
+use serde::Deserialize;
+
+use crate::{AxiomProvider, DbProvider, LogProvider, Logger};
+
+/// Declarative shape of a `Logger` config file: a flat `buffer_timing`/
+/// `level`, and one `[[providers]]` entry per provider, each naming a
+/// `kind` (`axiom`, `db`, ...) plus that provider's own settings.
+#[derive(Debug, Deserialize)]
+struct LoggerConfigFile {
+    buffer_timing: u64,
+    level: String,
+    providers: Vec<ProviderConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProviderConfig {
+    kind: String,
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(flatten)]
+    settings: HashMap<String, String>,
+}
+
+/// Parses a provider's optional `level` key into a `LevelFilter`, for
+/// `with_level`. Absent means the provider keeps its own default.
+fn parse_provider_level(provider_config: &ProviderConfig) -> Result<Option<log::LevelFilter>> {
+    provider_config
+        .level
+        .as_deref()
+        .map(|level| {
+            level.parse().map_err(|_| {
+                anyhow!(
+                    "provider '{}' has invalid level '{}'",
+                    provider_config.kind,
+                    level
+                )
+            })
+        })
+        .transpose()
+}
+
+type ProviderFactory = fn(&ProviderConfig) -> Result<Arc<dyn LogProvider>>;
+
+fn provider_registry() -> HashMap<&'static str, ProviderFactory> {
+    let mut registry: HashMap<&'static str, ProviderFactory> = HashMap::new();
+    registry.insert("axiom", build_axiom_provider);
+    registry.insert("db", build_db_provider);
+    registry
+}
+
+/// Looks up `key` for a provider, letting `LOG_ANYWHERE_<KIND>_<KEY>`
+/// override whatever is in the config file. Used so secrets like the
+/// Axiom token don't have to live in the file on disk.
+fn resolve_setting(provider_config: &ProviderConfig, key: &str) -> Option<String> {
+    let env_key = format!(
+        "LOG_ANYWHERE_{}_{}",
+        provider_config.kind.to_uppercase(),
+        key.to_uppercase()
+    );
+    std::env::var(&env_key)
+        .ok()
+        .or_else(|| provider_config.settings.get(key).cloned())
+}
+
+fn required_setting(provider_config: &ProviderConfig, key: &str) -> Result<String> {
+    resolve_setting(provider_config, key).ok_or_else(|| {
+        anyhow!(
+            "provider '{}' is missing required setting '{}'",
+            provider_config.kind,
+            key
+        )
+    })
+}
+
+fn build_axiom_provider(provider_config: &ProviderConfig) -> Result<Arc<dyn LogProvider>> {
+    let auth_token = required_setting(provider_config, "auth_token")?;
+    let dataset = required_setting(provider_config, "dataset")?;
+    let mut provider = AxiomProvider::new(auth_token, dataset);
+    if let Some(level) = parse_provider_level(provider_config)? {
+        provider = provider.with_level(level);
+    }
+    Ok(Arc::new(provider))
+}
+
+fn build_db_provider(provider_config: &ProviderConfig) -> Result<Arc<dyn LogProvider>> {
+    let mut provider = DbProvider::new();
+    if let Some(level) = parse_provider_level(provider_config)? {
+        provider = provider.with_level(level);
+    }
+    Ok(provider)
+}
+
+impl Logger {
+    /// Builds a fully wired `Logger` from a TOML config file instead of
+    /// hand-building providers in code. Expects a `providers` table naming
+    /// each provider's `kind` plus its settings, and top-level
+    /// `buffer_timing`/`level` keys, e.g.:
+    ///
+    /// ```toml
+    /// buffer_timing = 5
+    /// level = "info"
+    ///
+    /// [[providers]]
+    /// kind = "axiom"
+    /// auth_token = "xaat-..."
+    /// dataset = "my-dataset"
+    /// level = "warn"
+    ///
+    /// [[providers]]
+    /// kind = "db"
+    /// ```
+    ///
+    /// Any provider setting its factory reads (`auth_token` and `dataset`
+    /// for `axiom`) can be overridden by a `LOG_ANYWHERE_<KIND>_<SETTING>`
+    /// environment variable, so secrets like the Axiom token don't have to
+    /// live in the file. A provider's `level` key and the top-level keys
+    /// are read from the file only.
+    ///
+    /// A provider's optional `level` key sets its own minimum level via
+    /// `LogProvider::with_level` (e.g. shipping only warnings to Axiom
+    /// while a local `db` provider gets everything); omit it to keep that
+    /// provider's default.
+    pub fn from_config(path: impl AsRef<Path>) -> Result<Self> {
+        let settings = Config::builder()
+            .add_source(config::File::from(path.as_ref()))
+            .build()?;
+
+        let raw: LoggerConfigFile = settings.try_deserialize()?;
+
+        let level: log::LevelFilter = raw
+            .level
+            .parse()
+            .map_err(|_| anyhow!("invalid log level '{}'", raw.level))?;
+
+        let registry = provider_registry();
+        let mut providers = Vec::with_capacity(raw.providers.len());
+        for provider_config in &raw.providers {
+            let factory = registry.get(provider_config.kind.as_str()).ok_or_else(|| {
+                anyhow!("unknown provider kind '{}'", provider_config.kind)
+            })?;
+            providers.push(factory(provider_config)?);
+        }
+
+        Ok(Logger::new(providers, raw.buffer_timing, level))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn write_temp_toml(name: &str, contents: &str) -> std::path::PathBuf {
+        let mut path = std::env::temp_dir();
+        path.push(format!("log_anywhere_test_{}_{}.toml", name, std::process::id()));
+        let mut file = std::fs::File::create(&path).unwrap();
+        file.write_all(contents.as_bytes()).unwrap();
+        path
+    }
+
+    #[test]
+    fn from_config_errors_on_missing_required_setting() {
+        let path = write_temp_toml(
+            "missing_setting",
+            r#"
+                buffer_timing = 5
+                level = "info"
+
+                [[providers]]
+                kind = "axiom"
+                dataset = "my-dataset"
+            "#,
+        );
+        let result = Logger::from_config(&path);
+        std::fs::remove_file(&path).unwrap();
+        let err = match result {
+            Ok(_) => panic!("expected error"),
+            Err(e) => e.to_string(),
+        };
+        assert!(err.contains("auth_token"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn from_config_errors_on_unknown_provider_kind() {
+        let path = write_temp_toml(
+            "unknown_kind",
+            r#"
+                buffer_timing = 5
+                level = "info"
+
+                [[providers]]
+                kind = "splunk"
+            "#,
+        );
+        let result = Logger::from_config(&path);
+        std::fs::remove_file(&path).unwrap();
+        let err = match result {
+            Ok(_) => panic!("expected error"),
+            Err(e) => e.to_string(),
+        };
+        assert!(err.contains("splunk"), "unexpected error: {}", err);
+    }
+
+    #[test]
+    fn resolve_setting_prefers_env_over_file() {
+        let provider_config = ProviderConfig {
+            kind: "axiom".to_string(),
+            level: None,
+            settings: HashMap::from([("auth_token".to_string(), "from-file".to_string())]),
+        };
+        unsafe { std::env::set_var("LOG_ANYWHERE_AXIOM_AUTH_TOKEN", "from-env") };
+        let resolved = resolve_setting(&provider_config, "auth_token");
+        unsafe { std::env::remove_var("LOG_ANYWHERE_AXIOM_AUTH_TOKEN") };
+        assert_eq!(resolved, Some("from-env".to_string()));
+    }
+}
@@ -1,6 +1,12 @@
 pub mod providers;
+mod config;
+mod panic_handler;
+mod retry;
 pub use crate::providers::prelude::*;
+pub use crate::panic_handler::PanicHandler;
+pub use crate::retry::RetryPolicy;
 
+use std::collections::VecDeque;
 use std::sync::{ Arc, Mutex };
 use std::time::Duration;
 use std::{ mem, panic };
@@ -13,63 +19,246 @@ use async_trait::async_trait;
 use tokio::task;
 use tokio::time::sleep;
 
+/// How long the panicking thread will wait for the panic-time flush to
+/// finish, and then on the `PanicHandler`'s `Condvar` for every provider
+/// to acknowledge a drained buffer, before giving up and letting the
+/// process continue unwinding. Each of the two waits gets this bound.
+const PANIC_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
 
+/// Cap on how many failed batches a single provider's `buffer_loop` will
+/// hold onto for retry before dropping the oldest one.
+const DEAD_LETTER_CAPACITY: usize = 50;
 
 #[async_trait]
 pub trait LogProvider: Send + Sync {
-    async fn send_log(&self, messages: Vec<LogAnywhereRecord>);
+    async fn send_log(&self, messages: Vec<LogAnywhereRecord>) -> Result<()>;
+
+    /// The minimum level this provider wants to receive, e.g. shipping
+    /// only `Warn`+ to a remote dataset while a local provider gets
+    /// everything. Defaults to accepting every level.
+    fn min_level(&self) -> log::LevelFilter {
+        log::LevelFilter::Trace
+    }
+}
+
+/// Sends `messages` through `provider`, retrying with exponential backoff
+/// and jitter per `retry_policy`. Returns the batch back on exhausted
+/// retries so the caller can dead-letter it instead of losing it.
+async fn send_with_retry(
+    provider: &Arc<dyn LogProvider>,
+    messages: Vec<LogAnywhereRecord>,
+    retry_policy: &RetryPolicy,
+) -> std::result::Result<(), Vec<LogAnywhereRecord>> {
+    let mut attempt = 0;
+    loop {
+        match provider.send_log(messages.clone()).await {
+            Ok(()) => return Ok(()),
+            Err(e) => {
+                attempt += 1;
+                if attempt >= retry_policy.max_attempts {
+                    eprintln!(
+                        "log_anywhere: giving up on batch after {} attempts: {}",
+                        attempt, e
+                    );
+                    return Err(messages);
+                }
+                let delay = retry_policy.delay_for(attempt);
+                eprintln!(
+                    "log_anywhere: send_log failed (attempt {}): {}; retrying in {:?}",
+                    attempt, e, delay
+                );
+                sleep(delay).await;
+            }
+        }
+    }
 }
 
 
+/// A registered provider paired with its own queue of pending records.
+/// Giving each provider its own buffer means `Logger::log` fans a record
+/// out to every provider, and a slow or failing provider's retry/dead-letter
+/// state can never starve the others. `dead_letters` is shared with
+/// `buffer_loop` (rather than kept as a loop-local variable) so panic-time
+/// flushing can see batches that exhausted their retries, not just what's
+/// still sitting in `buffer`. `provider_id` is this entry's index among
+/// `Logger::providers`, used to ack its drain with the `PanicHandler`.
+#[derive(Clone)]
+struct ProviderEntry {
+    provider: Arc<dyn LogProvider>,
+    provider_id: usize,
+    buffer: Arc<Mutex<Vec<LogAnywhereRecord>>>,
+    dead_letters: Arc<Mutex<VecDeque<Vec<LogAnywhereRecord>>>>,
+}
+
 #[derive(Clone)]
 pub struct Logger {
-    providers: Vec<Arc<dyn LogProvider>>,
-    log_buffer_records: Arc<Mutex<Vec<LogAnywhereRecord>>>,
+    providers: Vec<ProviderEntry>,
     buffer_timing: Arc<u64>,
-    buffer_emptied_on_panic: Arc<Mutex<bool>>,
+    retry_policy: RetryPolicy,
+    panic_handler: PanicHandler,
+    panic_flush: PanicFlushOptions,
     is_panicking: Arc<Mutex<bool>>,
-    level: Arc<log::LevelFilter> // TO DO: Make this into string slice
+    level: Arc<log::LevelFilter>
 }
 
 async fn buffer_loop(
-    log_buffer_records: Arc<Mutex<Vec<LogAnywhereRecord>>>, 
-    provider: Arc<dyn LogProvider>, 
+    entry: ProviderEntry,
     buffer_timing: Arc<u64>,
-    buffer_emptied_on_panic: Arc<Mutex<bool>>,
+    retry_policy: RetryPolicy,
+    panic_handler: PanicHandler,
     is_panicking: Arc<Mutex<bool>>
 ) {
+    let ProviderEntry { provider, provider_id, buffer, dead_letters } = entry;
+
     loop {
         let messages = {
-            let mut records_guard = log_buffer_records.lock().unwrap();
+            let mut records_guard = buffer.lock().unwrap();
             mem::take(&mut *records_guard)
         };
 
         if messages.len() > 0 {
-            provider.send_log(messages).await;
-
-            if *is_panicking.lock().unwrap() {
-                println!("panic state detected");
-                if log_buffer_records.lock().unwrap().len() == 0 {
-                    println!("buffer empty in panic, exiting");
-                    *buffer_emptied_on_panic.lock().unwrap() = true;
-                } else {
-                    println!("buffer not empty, waiting for next loop cycle to empty buffer");
-                }
+            dead_letters.lock().unwrap().push_back(messages);
+        }
+
+        // Flush as many dead-lettered batches as currently succeed, oldest
+        // first; stop at the first failure and retry it next cycle.
+        loop {
+            let batch = { dead_letters.lock().unwrap().pop_front() };
+            let batch = match batch {
+                Some(batch) => batch,
+                None => break,
+            };
+            if let Err(failed_batch) = send_with_retry(&provider, batch, &retry_policy).await {
+                dead_letters.lock().unwrap().push_front(failed_batch);
+                break;
+            }
+        }
+
+        {
+            let mut dead_letters_guard = dead_letters.lock().unwrap();
+            while dead_letters_guard.len() > DEAD_LETTER_CAPACITY {
+                dead_letters_guard.pop_front();
+                eprintln!("log_anywhere: dead-letter buffer full, dropping oldest batch");
+            }
+        }
+
+        if *is_panicking.lock().unwrap() {
+            println!("panic state detected");
+            if dead_letters.lock().unwrap().is_empty() && buffer.lock().unwrap().len() == 0 {
+                println!("buffer empty in panic, exiting");
+                panic_handler.acknowledge_drain(provider_id);
+            } else {
+                println!("buffer not empty, waiting for next loop cycle to empty buffer");
             }
         }
+
         sleep(Duration::from_secs(*buffer_timing)).await
     }
 }
 
+/// Whether `set_panic_hook` echoes the buffered records it ships at panic
+/// time to stderr, in a clearly delimited block, alongside the panic
+/// message itself.
+#[derive(Clone, Copy, Debug)]
+pub struct PanicFlushOptions {
+    pub dump_to_stderr: bool,
+}
+
+impl Default for PanicFlushOptions {
+    fn default() -> Self {
+        PanicFlushOptions {
+            dump_to_stderr: true,
+        }
+    }
+}
+
+/// Best-effort, synchronous drain of every provider's buffer (and any
+/// batches sitting in its dead-letter queue after exhausting their
+/// retries) at panic time. Runs on a dedicated thread (so it works
+/// whether or not the panicking thread is itself a Tokio worker) and is
+/// bounded by `timeout`, since this is crash-time cleanup, not a guarantee.
+///
+/// Acknowledges each provider's drain against `panic_handler` itself,
+/// right after taking its buffer/dead-letters, instead of waiting for
+/// `buffer_loop`'s own timer-gated ack — otherwise `wait_for_drain` would
+/// sit out the full timeout on every panic even though the real flush
+/// already completed.
+fn flush_on_panic(
+    providers: &[ProviderEntry],
+    panic_handler: &PanicHandler,
+    options: PanicFlushOptions,
+    timeout: Duration,
+) {
+    let batches: Vec<(Arc<dyn LogProvider>, Vec<LogAnywhereRecord>)> = providers
+        .iter()
+        .map(|entry| {
+            let mut records = {
+                let mut guard = entry.buffer.lock().unwrap();
+                mem::take(&mut *guard)
+            };
+
+            let mut dead_letters_guard = entry.dead_letters.lock().unwrap();
+            while let Some(batch) = dead_letters_guard.pop_front() {
+                records.extend(batch);
+            }
+            drop(dead_letters_guard);
+
+            panic_handler.acknowledge_drain(entry.provider_id);
+
+            (entry.provider.clone(), records)
+        })
+        .collect();
+
+    if options.dump_to_stderr {
+        dump_to_stderr(&batches);
+    }
+
+    let (done_tx, done_rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build();
+        match runtime {
+            Ok(runtime) => runtime.block_on(async {
+                for (provider, batch) in batches {
+                    if batch.is_empty() {
+                        continue;
+                    }
+                    if let Err(e) = provider.send_log(batch).await {
+                        eprintln!("log_anywhere: panic-time flush failed: {}", e);
+                    }
+                }
+            }),
+            Err(e) => eprintln!("log_anywhere: could not start panic-flush runtime: {}", e),
+        }
+        let _ = done_tx.send(());
+    });
+
+    // bounded wait: this is best-effort cleanup, not a guarantee
+    let _ = done_rx.recv_timeout(timeout);
+}
+
+fn dump_to_stderr(batches: &[(Arc<dyn LogProvider>, Vec<LogAnywhereRecord>)]) {
+    eprintln!("---- log_anywhere: buffered records at panic ----");
+    for (_, batch) in batches {
+        for record in batch {
+            eprintln!(
+                "{} | message: {} | file: {:?} | line: {:?}",
+                record.level, record.message, record.file, record.line
+            );
+        }
+    }
+    eprintln!("---- end log_anywhere buffered records ----");
+}
+
 fn set_panic_hook (
-    log_buffer_records: Arc<Mutex<Vec<LogAnywhereRecord>>>,
-    buffer_emptied_on_panic: Arc<Mutex<bool>>,
-    is_panicking: Arc<Mutex<bool>>
+    providers: Vec<ProviderEntry>,
+    panic_handler: PanicHandler,
+    is_panicking: Arc<Mutex<bool>>,
+    flush_options: PanicFlushOptions
 ) {
 
     panic::set_hook(Box::new(move |p| {
         *is_panicking.lock().unwrap() = true;
-        
+
         eprintln!("{}", p);
         eprintln!("waiting for log_anywhere to cleanup, 1 second please");
 
@@ -83,10 +272,23 @@ fn set_panic_hook (
             line
         };
 
-        log_buffer_records.lock().unwrap().push(anywhere_log);
+        for entry in &providers {
+            entry.buffer.lock().unwrap().push(anywhere_log.clone());
+        }
+
+        // run any cleanup callbacks (flush metrics, close DB handles, etc.)
+        // registered via `Logger::on_panic` before we wait on the providers
+        panic_handler.run_listeners();
+
+        // ship everything still buffered right now, instead of relying on
+        // the normal buffer_loop timing, which can lose up to
+        // buffer_timing seconds of context if the process tears down first
+        flush_on_panic(&providers, &panic_handler, flush_options, PANIC_DRAIN_TIMEOUT);
 
-        // loop infinitely until buffer is emptied
-        while !*buffer_emptied_on_panic.lock().unwrap() {}
+        // block cheaply until every provider has acknowledged a drained
+        // buffer, instead of spinning on a busy-wait; flush_on_panic has
+        // already acknowledged each provider, so this normally returns at once
+        panic_handler.wait_for_drain(PANIC_DRAIN_TIMEOUT);
     }));
 }
 
@@ -109,33 +311,80 @@ impl Logger {
         buffer_timing: u64,
         level: log::LevelFilter
     ) -> Self {
+        let providers = providers
+            .into_iter()
+            .enumerate()
+            .map(|(provider_id, provider)| ProviderEntry {
+                provider,
+                provider_id,
+                buffer: Arc::new(Mutex::new(Vec::new())),
+                dead_letters: Arc::new(Mutex::new(VecDeque::new())),
+            })
+            .collect();
+
         Logger {
             providers,
-            log_buffer_records: Arc::new(Mutex::new(Vec::new())),
             buffer_timing: Arc::new(buffer_timing),
-            buffer_emptied_on_panic: Arc::new(Mutex::new(false)),
+            retry_policy: RetryPolicy::default(),
+            panic_handler: PanicHandler::new(),
+            panic_flush: PanicFlushOptions::default(),
             is_panicking: Arc::new(Mutex::new(false)),
             level: Arc::new(level),
         }
     }
 
+    /// Overrides the default `RetryPolicy` used by every provider's
+    /// `buffer_loop` when `send_log` fails (max attempts, base/max delay).
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Toggles whether the panic hook echoes the buffered records it
+    /// ships at panic time to stderr, in a clearly delimited block.
+    /// Enabled by default.
+    pub fn with_panic_stderr_dump(mut self, enabled: bool) -> Self {
+        self.panic_flush.dump_to_stderr = enabled;
+        self
+    }
+
+    /// Registers a callback to run inside the panic hook, after the panic
+    /// has been recorded but before the process continues unwinding.
+    /// Delegates to the `Logger`'s own `PanicHandler`; see
+    /// [`PanicHandler::on_panic`].
+    pub fn on_panic<F>(&self, listener: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.panic_handler.on_panic(listener);
+    }
+
+    /// Exposes the `Logger`'s `PanicHandler` so it can be chained with
+    /// handlers owned by other components via `PanicHandler::forward_from`.
+    pub fn panic_handler(&self) -> &PanicHandler {
+        &self.panic_handler
+    }
+
     pub fn init(self: Self) -> Result<(), SetLoggerError> {
         let level_ptr = Arc::clone(&self.level);
 
-        // set panic hook
+        self.panic_handler.set_expected_drains(self.providers.len());
+
+        // set panic hook, fanning the panic record out to every provider's buffer
         set_panic_hook(
-            self.log_buffer_records.clone(), 
-            self.buffer_emptied_on_panic.clone(), 
-            self.is_panicking.clone()
+            self.providers.clone(),
+            self.panic_handler.clone(),
+            self.is_panicking.clone(),
+            self.panic_flush
         );
 
-        for provider in &self.providers {
+        for entry in &self.providers {
             task::spawn(
                 buffer_loop(
-                    self.log_buffer_records.clone(), 
-                    provider.clone(),
-                    self.buffer_timing.clone(), 
-                    self.buffer_emptied_on_panic.clone(), 
+                    entry.clone(),
+                    self.buffer_timing.clone(),
+                    self.retry_policy,
+                    self.panic_handler.clone(),
                     self.is_panicking.clone()
                 )
             );
@@ -148,7 +397,7 @@ impl Logger {
     }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct LogAnywhereRecord {
     pub level: String,
     pub message: String,
@@ -161,10 +410,14 @@ unsafe impl Send for Logger {}
 
 impl Log for Logger {
     fn enabled(&self, metadata: &log::Metadata) -> bool {
-        true
+        metadata.level() <= *self.level
     }
 
     fn log(&self, record: &log::Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
         let file = match record.file() {
             Some(f) => Some(f.to_string()),
             None => None
@@ -178,7 +431,14 @@ impl Log for Logger {
         };
 
         println!("{} | message: {} | line: {:?}", anywhere_log.level, anywhere_log.message, anywhere_log.line);
-        self.log_buffer_records.lock().unwrap().push(anywhere_log);
+
+        // fan the record out to every provider's own buffer, but only the
+        // providers whose own minimum level accepts it
+        for entry in &self.providers {
+            if record.level() <= entry.provider.min_level() {
+                entry.buffer.lock().unwrap().push(anywhere_log.clone());
+            }
+        }
     }
 
     fn flush(&self) {
@@ -197,5 +457,66 @@ mod tests {
         // let result = init(2, 2);
         assert_eq!(4, 4);
     }
+
+    /// A no-op `LogProvider` for exercising `Logger::log`'s fan-out and
+    /// level-filtering behavior without a network call.
+    struct StubProvider {
+        min_level: log::LevelFilter,
+    }
+
+    #[async_trait]
+    impl LogProvider for StubProvider {
+        async fn send_log(&self, _messages: Vec<LogAnywhereRecord>) -> Result<()> {
+            Ok(())
+        }
+
+        fn min_level(&self) -> log::LevelFilter {
+            self.min_level
+        }
+    }
+
+    fn info_record() -> log::Record<'static> {
+        log::Record::builder()
+            .level(log::Level::Info)
+            .args(format_args!("hello"))
+            .build()
+    }
+
+    #[test]
+    fn log_fans_a_record_out_to_every_provider_buffer() {
+        let provider_a: Arc<dyn LogProvider> = Arc::new(StubProvider { min_level: log::LevelFilter::Trace });
+        let provider_b: Arc<dyn LogProvider> = Arc::new(StubProvider { min_level: log::LevelFilter::Trace });
+        let logger = Logger::new(vec![provider_a, provider_b], 5, log::LevelFilter::Info);
+
+        logger.log(&info_record());
+
+        for entry in &logger.providers {
+            assert_eq!(entry.buffer.lock().unwrap().len(), 1);
+        }
+    }
+
+    #[test]
+    fn log_skips_providers_whose_min_level_rejects_the_record() {
+        let strict: Arc<dyn LogProvider> = Arc::new(StubProvider { min_level: log::LevelFilter::Warn });
+        let permissive: Arc<dyn LogProvider> = Arc::new(StubProvider { min_level: log::LevelFilter::Trace });
+        let logger = Logger::new(vec![strict, permissive], 5, log::LevelFilter::Trace);
+
+        logger.log(&info_record());
+
+        assert_eq!(logger.providers[0].buffer.lock().unwrap().len(), 0);
+        assert_eq!(logger.providers[1].buffer.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn enabled_respects_the_logger_global_level() {
+        let provider: Arc<dyn LogProvider> = Arc::new(StubProvider { min_level: log::LevelFilter::Trace });
+        let logger = Logger::new(vec![provider], 5, log::LevelFilter::Warn);
+
+        let info_metadata = log::Metadata::builder().level(log::Level::Info).build();
+        assert!(!logger.enabled(&info_metadata));
+
+        let warn_metadata = log::Metadata::builder().level(log::Level::Warn).build();
+        assert!(logger.enabled(&warn_metadata));
+    }
 }
 
@@ -0,0 +1,170 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::Duration;
+
+type PanicListener = Box<dyn Fn() + Send + Sync>;
+
+#[derive(Default)]
+struct DrainState {
+    expected: usize,
+    acknowledged: HashSet<usize>,
+}
+
+impl DrainState {
+    fn is_drained(&self) -> bool {
+        self.acknowledged.len() >= self.expected
+    }
+}
+
+/// Coordinates graceful shutdown on panic.
+///
+/// Modeled on OpenEthereum's `PanicHandler`: other components register
+/// cleanup callbacks via `on_panic`, and `forward_from` lets one handler
+/// relay another's panics so handlers owned by different parts of an
+/// application can be chained together.
+#[derive(Clone)]
+pub struct PanicHandler {
+    listeners: Arc<Mutex<Vec<PanicListener>>>,
+    drain_state: Arc<Mutex<DrainState>>,
+    drain_condvar: Arc<Condvar>,
+}
+
+impl PanicHandler {
+    pub fn new() -> Self {
+        PanicHandler {
+            listeners: Arc::new(Mutex::new(Vec::new())),
+            drain_state: Arc::new(Mutex::new(DrainState::default())),
+            drain_condvar: Arc::new(Condvar::new()),
+        }
+    }
+
+    /// Registers a callback that runs inside the panic hook, after the
+    /// panic has been recorded but before the process continues
+    /// unwinding. Useful for flushing metrics, closing DB handles, etc.
+    pub fn on_panic<F>(&self, listener: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        self.listeners.lock().unwrap().push(Box::new(listener));
+    }
+
+    /// Relays panics observed by `other` into this handler, so a single
+    /// `PanicHandler` can coordinate shutdown across components that each
+    /// own their own handler.
+    pub fn forward_from(&self, other: &PanicHandler) {
+        let this = self.clone();
+        other.on_panic(move || this.run_listeners());
+    }
+
+    /// Sets how many providers must acknowledge a drained buffer before
+    /// `wait_for_drain` unblocks.
+    pub(crate) fn set_expected_drains(&self, count: usize) {
+        self.drain_state.lock().unwrap().expected = count;
+    }
+
+    /// Called once a provider's buffer is empty during a panic, both by
+    /// the panic-time flush after it takes the buffer and by the
+    /// provider's `buffer_loop`. Unblocks `wait_for_drain` once every
+    /// provider has checked in.
+    pub(crate) fn acknowledge_drain(&self, provider_id: usize) {
+        let mut state = self.drain_state.lock().unwrap();
+        state.acknowledged.insert(provider_id);
+        if state.is_drained() {
+            self.drain_condvar.notify_all();
+        }
+    }
+
+    pub(crate) fn run_listeners(&self) {
+        for listener in self.listeners.lock().unwrap().iter() {
+            listener();
+        }
+    }
+
+    /// Blocks the panicking thread until every registered provider has
+    /// acknowledged a drained buffer, or `timeout` elapses, whichever
+    /// comes first. Cheap to wait on since it parks on a `Condvar` rather
+    /// than spinning.
+    pub(crate) fn wait_for_drain(&self, timeout: Duration) {
+        let state = self.drain_state.lock().unwrap();
+        if state.is_drained() {
+            return;
+        }
+        let _ = self
+            .drain_condvar
+            .wait_timeout_while(state, timeout, |state| !state.is_drained())
+            .unwrap();
+    }
+}
+
+impl Default for PanicHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wait_for_drain_requires_every_provider_to_ack() {
+        let handler = PanicHandler::new();
+        handler.set_expected_drains(2);
+        handler.acknowledge_drain(0);
+
+        let start = std::time::Instant::now();
+        handler.wait_for_drain(Duration::from_millis(50));
+        assert!(
+            start.elapsed() >= Duration::from_millis(50),
+            "wait_for_drain returned early with only one of two providers acked"
+        );
+    }
+
+    #[test]
+    fn wait_for_drain_unblocks_once_every_provider_has_acked() {
+        let handler = PanicHandler::new();
+        handler.set_expected_drains(2);
+
+        let other = handler.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(20));
+            other.acknowledge_drain(0);
+            other.acknowledge_drain(1);
+        });
+
+        let start = std::time::Instant::now();
+        handler.wait_for_drain(Duration::from_secs(5));
+        assert!(
+            start.elapsed() < Duration::from_secs(1),
+            "wait_for_drain did not unblock promptly once every provider acked"
+        );
+    }
+
+    #[test]
+    fn forward_from_relays_panics_into_the_target_handler_listeners() {
+        let target = PanicHandler::new();
+        let source = PanicHandler::new();
+        target.forward_from(&source);
+
+        let ran = Arc::new(Mutex::new(false));
+        let ran_handle = ran.clone();
+        target.on_panic(move || *ran_handle.lock().unwrap() = true);
+
+        source.run_listeners();
+
+        assert!(*ran.lock().unwrap());
+    }
+
+    #[test]
+    fn on_panic_listeners_run_when_run_listeners_is_called() {
+        let handler = PanicHandler::new();
+
+        let calls = Arc::new(Mutex::new(0));
+        let calls_handle = calls.clone();
+        handler.on_panic(move || *calls_handle.lock().unwrap() += 1);
+
+        handler.run_listeners();
+
+        assert_eq!(*calls.lock().unwrap(), 1);
+    }
+}
@@ -1,25 +1,41 @@
+use std::sync::Mutex;
+
 use reqwest::header::{AUTHORIZATION, CONTENT_TYPE};
 use reqwest::header;
 use crate::{LogProvider, LogAnywhereRecord};
+use anyhow::{anyhow, Result};
 use async_trait::async_trait;
 
 pub struct AxiomProvider {
     auth_token: String,
-    dataset: String
+    dataset: String,
+    min_level: Mutex<log::LevelFilter>
 }
 
 impl AxiomProvider {
     pub fn new(auth_token: String, dataset: String) -> AxiomProvider {
         AxiomProvider {
             auth_token,
-            dataset
+            dataset,
+            min_level: Mutex::new(log::LevelFilter::Trace)
         }
     }
+
+    /// Sets the minimum level this provider wants to receive, e.g. `Warn`
+    /// so only warnings and errors get shipped to Axiom.
+    pub fn with_level(self, level: log::LevelFilter) -> Self {
+        *self.min_level.lock().unwrap() = level;
+        self
+    }
 }
 
 #[async_trait]
 impl LogProvider for AxiomProvider {
-    async fn send_log(&self, messages: Vec<LogAnywhereRecord>) {
+    fn min_level(&self) -> log::LevelFilter {
+        *self.min_level.lock().unwrap()
+    }
+
+    async fn send_log(&self, messages: Vec<LogAnywhereRecord>) -> Result<()> {
         let mut headers = header::HeaderMap::new();
         headers.insert(AUTHORIZATION, format!("Bearer {}", &self.auth_token).parse().unwrap());
         headers.insert(CONTENT_TYPE, "application/json".parse().unwrap());
@@ -30,13 +46,16 @@ impl LogProvider for AxiomProvider {
             .headers(headers)
             .json(&messages)
             .send()
-            .await;
+            .await
+            .map_err(|e| anyhow!("axiom request failed: {}", e))?;
 
-        match res {
-            Ok(res) => println!("res: {:?}", res.text().await.unwrap()),
-            Err(e) => {
-                println!("error status: {:?}, error: {:?}", e.status(), e)
-            }
+        if !res.status().is_success() {
+            let status = res.status();
+            let body = res.text().await.unwrap_or_default();
+            return Err(anyhow!("axiom ingest returned {}: {}", status, body));
         }
+
+        println!("res: {:?}", res.text().await.unwrap_or_default());
+        Ok(())
     }
 }
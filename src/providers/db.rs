@@ -1,24 +1,38 @@
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
+use anyhow::Result;
 use async_trait::async_trait;
 use crate::{LogProvider, LogAnywhereRecord};
 
 pub struct DbProvider {
-    db_conn: String
+    db_conn: String,
+    min_level: Mutex<log::LevelFilter>
 }
 
 impl DbProvider {
     pub fn new() -> Arc<DbProvider> {
         let db_conn = "".to_string();
         Arc::new(DbProvider {
-            db_conn
+            db_conn,
+            min_level: Mutex::new(log::LevelFilter::Trace)
         })
     }
+
+    /// Sets the minimum level this provider wants to receive.
+    pub fn with_level(self: Arc<Self>, level: log::LevelFilter) -> Arc<Self> {
+        *self.min_level.lock().unwrap() = level;
+        self
+    }
 }
 
 #[async_trait]
 impl LogProvider for DbProvider {
-    async fn send_log(&self, messages: Vec<LogAnywhereRecord>) {
+    fn min_level(&self) -> log::LevelFilter {
+        *self.min_level.lock().unwrap()
+    }
+
+    async fn send_log(&self, messages: Vec<LogAnywhereRecord>) -> Result<()> {
         println!("DB logged for DB: {:?}", messages);
+        Ok(())
     }
 }
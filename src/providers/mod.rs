@@ -0,0 +1,7 @@
+pub mod axiom;
+pub mod db;
+
+pub mod prelude {
+    pub use super::axiom::AxiomProvider;
+    pub use super::db::DbProvider;
+}
@@ -0,0 +1,88 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Controls how `buffer_loop` retries a failed `LogProvider::send_log`
+/// call. The delay doubles per attempt up to `max_delay`, with full
+/// jitter applied so providers recovering from a shared outage don't all
+/// retry in lockstep.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay: Duration, max_delay: Duration) -> Self {
+        RetryPolicy {
+            max_attempts,
+            base_delay,
+            max_delay,
+        }
+    }
+
+    pub(crate) fn delay_for(&self, attempt: u32) -> Duration {
+        self.ceiling_for(attempt).mul_f64(Self::jitter_fraction())
+    }
+
+    /// The un-jittered delay ceiling for `attempt`: `base_delay` doubled
+    /// per attempt, capped at `max_delay`. `delay_for` samples a random
+    /// fraction of this; tests assert against this directly since it's
+    /// deterministic.
+    fn ceiling_for(&self, attempt: u32) -> Duration {
+        let shift = attempt.saturating_sub(1).min(31);
+        let exponential = self.base_delay.saturating_mul(1u32 << shift);
+        exponential.min(self.max_delay)
+    }
+
+    /// A cheap pseudo-random fraction in `[0.0, 1.0)`, good enough for
+    /// spreading out retries without pulling in a `rand` dependency.
+    fn jitter_fraction() -> f64 {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        (nanos % 1_000) as f64 / 1_000.0
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ceiling_grows_monotonically_before_capping() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(100), Duration::from_secs(10));
+        let mut previous = Duration::from_secs(0);
+        for attempt in 1..=5 {
+            let ceiling = policy.ceiling_for(attempt);
+            assert!(ceiling >= previous, "attempt {} did not grow", attempt);
+            previous = ceiling;
+        }
+    }
+
+    #[test]
+    fn ceiling_caps_at_max_delay() {
+        let policy = RetryPolicy::new(20, Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(policy.ceiling_for(20), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn delay_never_exceeds_its_ceiling() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(50), Duration::from_secs(5));
+        for attempt in 1..=8 {
+            let ceiling = policy.ceiling_for(attempt);
+            let delay = policy.delay_for(attempt);
+            assert!(delay <= ceiling, "attempt {} jittered above its ceiling", attempt);
+        }
+    }
+}